@@ -0,0 +1,269 @@
+// SPDX-License-Identifier: LGPL-2.1-only
+// Copyright (C) 2025 Collabora Ltd
+// Author: Denys Fedoryshchenko <denys.f@collabora.com>
+//
+// This library is free software; you can redistribute it and/or modify it under
+// the terms of the GNU Lesser General Public License as published by the Free
+// Software Foundation; version 2.1.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+// PARTICULAR PURPOSE. See the GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License along
+// with this library; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA
+
+/*
+In-process ACME (Let's Encrypt) certificate provisioning.
+
+Requests an HTTP-01 validated certificate via `instant-acme`, serving the
+challenge response from the same process on port 80, generates the
+keypair/CSR with `rcgen`, and persists the resulting `fullchain.pem` and
+`privkey.pem` into the configured directory. A background task watches the
+certificate's age and re-issues it before it expires, hot-reloading the
+`RustlsConfig` the HTTPS server already has in hand. This removes the need
+for an external certbot process and the `wait_for_file` polling it used to
+require.
+*/
+
+use axum_server::tls_rustls::RustlsConfig;
+use instant_acme::{
+    Account, AuthorizationStatus, ChallengeType, Identifier, LetsEncrypt, NewAccount, NewOrder,
+    OrderStatus,
+};
+use rcgen::{CertificateParams, DistinguishedName, KeyPair};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+use x509_parser::pem::parse_x509_pem;
+
+// Renew once fewer than this many days remain until the certificate's
+// notAfter, read back from the on-disk fullchain.pem rather than tracked
+// in memory, so renewal still fires correctly after a process restart.
+const RENEW_BEFORE_DAYS: u64 = 30;
+const RENEW_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Pending HTTP-01 challenge responses, keyed by token, shared with the
+/// `/.well-known/acme-challenge/:token` route.
+#[derive(Default, Clone)]
+pub struct ChallengeStore {
+    tokens: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl ChallengeStore {
+    pub fn get(&self, token: &str) -> Option<String> {
+        self.tokens.read().unwrap().get(token).cloned()
+    }
+
+    fn insert(&self, token: String, key_authorization: String) {
+        self.tokens.write().unwrap().insert(token, key_authorization);
+    }
+
+    fn remove(&self, token: &str) {
+        self.tokens.write().unwrap().remove(token);
+    }
+}
+
+pub struct AcmeManager {
+    domain: String,
+    email: String,
+    cert_dir: String,
+    challenges: ChallengeStore,
+}
+
+impl AcmeManager {
+    pub fn new(domain: String, email: String, cert_dir: String) -> Self {
+        AcmeManager {
+            domain,
+            email,
+            cert_dir,
+            challenges: ChallengeStore::default(),
+        }
+    }
+
+    pub fn challenges(&self) -> ChallengeStore {
+        self.challenges.clone()
+    }
+
+    fn cert_path(&self) -> String {
+        format!("{}/fullchain.pem", self.cert_dir)
+    }
+
+    fn key_path(&self) -> String {
+        format!("{}/privkey.pem", self.cert_dir)
+    }
+
+    /// Returns existing cert/key paths if a certificate is already on disk
+    /// and not yet due for renewal, otherwise requests a new one.
+    pub async fn ensure_certificate(&self) -> Result<(String, String), String> {
+        if std::path::Path::new(&self.cert_path()).exists()
+            && std::path::Path::new(&self.key_path()).exists()
+            && !self.needs_renewal()
+        {
+            println!("Using existing ACME certificate for {}", self.domain);
+            return Ok((self.cert_path(), self.key_path()));
+        }
+        self.issue_certificate().await?;
+        Ok((self.cert_path(), self.key_path()))
+    }
+
+    fn needs_renewal(&self) -> bool {
+        let not_after = match read_not_after(&self.cert_path()) {
+            Ok(not_after) => not_after,
+            Err(e) => {
+                eprintln!(
+                    "Warning: failed to read certificate expiry for {}, renewing to be safe: {}",
+                    self.cert_path(),
+                    e
+                );
+                return true;
+            }
+        };
+        let remaining = not_after
+            .duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO);
+        remaining <= Duration::from_secs(RENEW_BEFORE_DAYS * 24 * 3600)
+    }
+
+    async fn issue_certificate(&self) -> Result<(), String> {
+        println!("Requesting ACME certificate for {}", self.domain);
+        let (account, _credentials) = Account::create(
+            &NewAccount {
+                contact: &[&format!("mailto:{}", self.email)],
+                terms_of_service_agreed: true,
+                only_return_existing: false,
+            },
+            LetsEncrypt::Production.url(),
+            None,
+        )
+        .await
+        .map_err(|e| format!("failed to create ACME account: {e}"))?;
+
+        let identifier = Identifier::Dns(self.domain.clone());
+        let mut order = account
+            .new_order(&NewOrder {
+                identifiers: &[identifier],
+            })
+            .await
+            .map_err(|e| format!("failed to place ACME order: {e}"))?;
+
+        let authorizations = order
+            .authorizations()
+            .await
+            .map_err(|e| format!("failed to fetch authorizations: {e}"))?;
+
+        for authz in &authorizations {
+            if authz.status == AuthorizationStatus::Valid {
+                continue;
+            }
+            let challenge = authz
+                .challenges
+                .iter()
+                .find(|c| c.r#type == ChallengeType::Http01)
+                .ok_or("no HTTP-01 challenge offered")?;
+            let key_auth = order.key_authorization(challenge);
+            self.challenges
+                .insert(challenge.token.clone(), key_auth.as_str().to_string());
+
+            order
+                .set_challenge_ready(&challenge.url)
+                .await
+                .map_err(|e| format!("failed to mark challenge ready: {e}"))?;
+
+            let status = poll_until_ready(&mut order).await?;
+            self.challenges.remove(&challenge.token);
+            if status != OrderStatus::Ready && status != OrderStatus::Valid {
+                return Err(format!("ACME order did not become ready: {:?}", status));
+            }
+        }
+
+        let mut params = CertificateParams::new(vec![self.domain.clone()])
+            .map_err(|e| format!("invalid domain name: {e}"))?;
+        params.distinguished_name = DistinguishedName::new();
+        let key_pair = KeyPair::generate().map_err(|e| format!("failed to generate key: {e}"))?;
+        let csr = params
+            .serialize_request(&key_pair)
+            .map_err(|e| format!("failed to build CSR: {e}"))?;
+
+        order
+            .finalize(csr.der())
+            .await
+            .map_err(|e| format!("failed to finalize order: {e}"))?;
+        let cert_chain_pem = order
+            .certificate()
+            .await
+            .map_err(|e| format!("failed to fetch certificate: {e}"))?
+            .ok_or("ACME order finalized without a certificate")?;
+
+        std::fs::create_dir_all(&self.cert_dir)
+            .map_err(|e| format!("failed to create {}: {e}", self.cert_dir))?;
+        std::fs::write(self.cert_path(), cert_chain_pem)
+            .map_err(|e| format!("failed to write {}: {e}", self.cert_path()))?;
+        std::fs::write(self.key_path(), key_pair.serialize_pem())
+            .map_err(|e| format!("failed to write {}: {e}", self.key_path()))?;
+        println!("ACME certificate issued for {}", self.domain);
+        Ok(())
+    }
+
+    /// Spawn a background task that re-issues the certificate before it
+    /// expires and hot-reloads `tls_config` in place.
+    pub fn spawn_renewal_task(self: Arc<Self>, tls_config: RustlsConfig) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(RENEW_CHECK_INTERVAL).await;
+                if !self.needs_renewal() {
+                    continue;
+                }
+                match self.issue_certificate().await {
+                    Ok(()) => {
+                        if let Err(e) = tls_config
+                            .reload_from_pem_file(self.cert_path(), self.key_path())
+                            .await
+                        {
+                            eprintln!("Warning: failed to reload renewed certificate: {}", e);
+                        } else {
+                            println!("Reloaded renewed ACME certificate for {}", self.domain);
+                        }
+                    }
+                    Err(e) => eprintln!("Warning: ACME renewal failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+// Reads the notAfter of the leaf certificate out of a PEM chain on disk.
+// Used instead of an in-memory issue timestamp so renewal still fires on
+// schedule for a certificate issued by a previous run of the process.
+fn read_not_after(cert_path: &str) -> Result<SystemTime, String> {
+    let pem_bytes =
+        std::fs::read(cert_path).map_err(|e| format!("failed to read {cert_path}: {e}"))?;
+    let (_, pem) = parse_x509_pem(&pem_bytes)
+        .map_err(|e| format!("failed to parse PEM {cert_path}: {e}"))?;
+    let cert = pem
+        .parse_x509()
+        .map_err(|e| format!("failed to parse certificate {cert_path}: {e}"))?;
+    let not_after_secs = cert.validity().not_after.timestamp();
+    let not_after_secs: u64 = not_after_secs
+        .try_into()
+        .map_err(|_| format!("certificate {cert_path} has a notAfter before the epoch"))?;
+    Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(not_after_secs))
+}
+
+async fn poll_until_ready(order: &mut instant_acme::Order) -> Result<OrderStatus, String> {
+    for _ in 0..30 {
+        let state = order
+            .refresh()
+            .await
+            .map_err(|e| format!("failed to poll ACME order: {e}"))?;
+        match state.status {
+            OrderStatus::Pending | OrderStatus::Processing => {
+                tokio::time::sleep(Duration::from_secs(2)).await;
+            }
+            other => return Ok(other),
+        }
+    }
+    Err("timed out waiting for ACME order to become ready".to_string())
+}
@@ -0,0 +1,202 @@
+// SPDX-License-Identifier: LGPL-2.1-only
+// Copyright (C) 2025 Collabora Ltd
+// Author: Denys Fedoryshchenko <denys.f@collabora.com>
+//
+// This library is free software; you can redistribute it and/or modify it under
+// the terms of the GNU Lesser General Public License as published by the Free
+// Software Foundation; version 2.1.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+// PARTICULAR PURPOSE. See the GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License along
+// with this library; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA
+
+/*
+JSON Web Key Set support.
+
+Loads a JWKS from a local file or a periodically refreshed HTTPS endpoint
+and reconstructs jsonwebtoken DecodingKeys keyed by `kid`, so tokens signed
+with per-origin RSA/EC keypairs can be verified without a shared secret.
+*/
+
+use jsonwebtoken::{Algorithm, DecodingKey};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+
+#[derive(Debug, Deserialize)]
+struct RawJwk {
+    kty: String,
+    kid: Option<String>,
+    alg: Option<String>,
+    crv: Option<String>,
+    x: Option<String>,
+    y: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawJwkSet {
+    keys: Vec<RawJwk>,
+}
+
+/// A single verification key resolved from a JWKS entry.
+#[derive(Clone)]
+pub struct Key {
+    pub algorithm: Algorithm,
+    pub decoding_key: DecodingKey,
+}
+
+fn decode_key(raw: &RawJwk) -> Result<Key, String> {
+    match raw.kty.as_str() {
+        "EC" => {
+            let crv = raw.crv.as_deref().ok_or("EC key missing crv")?;
+            let x = raw.x.as_deref().ok_or("EC key missing x")?;
+            let y = raw.y.as_deref().ok_or("EC key missing y")?;
+            let algorithm = match crv {
+                "P-256" => Algorithm::ES256,
+                "P-384" => Algorithm::ES384,
+                other => return Err(format!("unsupported EC curve {other}")),
+            };
+            let decoding_key = DecodingKey::from_ec_components(x, y)
+                .map_err(|e| format!("invalid EC key: {e}"))?;
+            Ok(Key {
+                algorithm,
+                decoding_key,
+            })
+        }
+        "RSA" => {
+            let n = raw.n.as_deref().ok_or("RSA key missing n")?;
+            let e = raw.e.as_deref().ok_or("RSA key missing e")?;
+            let algorithm = match raw.alg.as_deref() {
+                Some("RS384") => Algorithm::RS384,
+                Some("RS512") => Algorithm::RS512,
+                _ => Algorithm::RS256,
+            };
+            let decoding_key = DecodingKey::from_rsa_components(n, e)
+                .map_err(|e| format!("invalid RSA key: {e}"))?;
+            Ok(Key {
+                algorithm,
+                decoding_key,
+            })
+        }
+        other => Err(format!("unsupported key type {other}")),
+    }
+}
+
+enum JwksSource {
+    File(String),
+    Url(String),
+}
+
+/// In-memory keyset, keyed by `kid`, refreshed from a file or URL.
+pub struct JwksStore {
+    keys: RwLock<BTreeMap<String, Key>>,
+    source: JwksSource,
+}
+
+impl JwksStore {
+    pub fn from_file(path: String) -> Self {
+        JwksStore {
+            keys: RwLock::new(BTreeMap::new()),
+            source: JwksSource::File(path),
+        }
+    }
+
+    pub fn from_url(url: String) -> Self {
+        JwksStore {
+            keys: RwLock::new(BTreeMap::new()),
+            source: JwksSource::Url(url),
+        }
+    }
+
+    /// Discover an OIDC provider's `jwks_uri` from its
+    /// `/.well-known/openid-configuration` document and build a store that
+    /// refreshes from it.
+    pub async fn from_oidc_issuer(issuer: &str) -> Result<Self, String> {
+        let discovery_url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+        let body = reqwest::get(&discovery_url)
+            .await
+            .map_err(|e| format!("failed to fetch OIDC discovery document {discovery_url}: {e}"))?
+            .text()
+            .await
+            .map_err(|e| format!("failed to read OIDC discovery document {discovery_url}: {e}"))?;
+        #[derive(Deserialize)]
+        struct Discovery {
+            jwks_uri: String,
+        }
+        let discovery: Discovery = serde_json::from_str(&body)
+            .map_err(|e| format!("failed to parse OIDC discovery document: {e}"))?;
+        Ok(JwksStore::from_url(discovery.jwks_uri))
+    }
+
+    /// Look up a key by `kid`. Returns `None` if it isn't (yet) known.
+    pub fn get(&self, kid: &str) -> Option<Key> {
+        self.keys.read().unwrap().get(kid).cloned()
+    }
+
+    /// Look up a key by `kid`, forcing a refresh first if it isn't cached.
+    /// Lets the provider rotate keys without an immediate client-side
+    /// refresh cycle: the first request for a new `kid` triggers the fetch.
+    pub async fn get_or_refresh(&self, kid: &str) -> Option<Key> {
+        if let Some(key) = self.get(kid) {
+            return Some(key);
+        }
+        if let Err(e) = self.refresh().await {
+            eprintln!("Warning: JWKS refresh on cache miss failed: {}", e);
+        }
+        self.get(kid)
+    }
+
+    /// Fetch the keyset from its source and replace the in-memory copy.
+    pub async fn refresh(&self) -> Result<(), String> {
+        let body = match &self.source {
+            JwksSource::File(path) => std::fs::read_to_string(path)
+                .map_err(|e| format!("failed to read JWKS file {path}: {e}"))?,
+            JwksSource::Url(url) => reqwest::get(url)
+                .await
+                .map_err(|e| format!("failed to fetch JWKS from {url}: {e}"))?
+                .text()
+                .await
+                .map_err(|e| format!("failed to read JWKS response from {url}: {e}"))?,
+        };
+        let raw: RawJwkSet =
+            serde_json::from_str(&body).map_err(|e| format!("failed to parse JWKS: {e}"))?;
+        let mut keys = BTreeMap::new();
+        for jwk in &raw.keys {
+            let Some(kid) = jwk.kid.clone() else {
+                continue;
+            };
+            match decode_key(jwk) {
+                Ok(key) => {
+                    keys.insert(kid, key);
+                }
+                Err(e) => eprintln!("Warning: skipping JWKS key {}: {}", kid, e),
+            }
+        }
+        println!("Loaded {} key(s) from JWKS", keys.len());
+        *self.keys.write().unwrap() = keys;
+        Ok(())
+    }
+
+    /// Spawn a background task that refreshes the keyset on a timer.
+    pub fn spawn_refresh_task(store: Arc<Self>, interval: Duration) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if let Err(e) = store.refresh().await {
+                    eprintln!("Warning: JWKS refresh failed: {}", e);
+                }
+            }
+        });
+    }
+}
@@ -26,21 +26,32 @@ KCIDB-Rust REST submissions receiver
 
 */
 
+mod acme;
+mod jwks;
+mod ratelimit;
+mod spool;
+mod validate;
+
 use axum::Router;
+use axum::extract::Path as AxumPath;
 use axum::extract::State;
+use axum::http::Method;
 use axum::http::StatusCode;
-use axum::http::header::HeaderMap;
+use axum::http::header::{self, HeaderMap, HeaderValue};
 use axum::response::IntoResponse;
 use axum::routing::{get, post};
 use axum_server::tls_rustls::RustlsConfig;
 use clap::Parser;
-use jsonwebtoken::{DecodingKey, Validation, decode};
+use jsonwebtoken::{DecodingKey, Validation, decode, decode_header};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::net::TcpListener;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::limit::RequestBodyLimitLayer;
 
 
@@ -59,24 +70,143 @@ struct Args {
     /// JWT secret
     #[clap(short, long, default_value = "secret")]
     jwt_secret: String,
+    /// Path to a local JWKS file, used to verify asymmetric (RS256/ES256) JWTs
+    #[clap(long)]
+    jwks_path: Option<String>,
+    /// HTTPS URL to fetch a JWKS from, refreshed periodically
+    #[clap(long)]
+    jwks_url: Option<String>,
+    /// How often to refresh the JWKS, in seconds
+    #[clap(long, default_value = "300")]
+    jwks_refresh_secs: u64,
+    /// Domain to provision a Let's Encrypt certificate for via ACME,
+    /// instead of loading certbot-produced files
+    #[clap(long)]
+    acme_domain: Option<String>,
+    /// Contact email registered with the ACME account (required with
+    /// `--acme-domain`)
+    #[clap(long)]
+    acme_email: Option<String>,
+    /// S3 bucket to use as the submission spool, instead of the local
+    /// filesystem directory
+    #[clap(long)]
+    spool_s3_bucket: Option<String>,
+    /// Custom S3 endpoint URL (for S3-compatible stores); defaults to AWS
+    #[clap(long)]
+    spool_s3_endpoint: Option<String>,
+    /// S3 region for the spool bucket
+    #[clap(long, default_value = "us-east-1")]
+    spool_s3_region: String,
+    /// OIDC issuer URL; enables bearer-token auth via the provider's
+    /// discovery document and JWKS instead of `--jwt-secret`/`--jwks-*`
+    #[clap(long)]
+    oidc_issuer: Option<String>,
+    /// Expected `aud` claim for OIDC bearer tokens (required with
+    /// `--oidc-issuer`)
+    #[clap(long)]
+    oidc_audience: Option<String>,
+    /// How often to refresh the OIDC provider's JWKS, in seconds
+    #[clap(long, default_value = "300")]
+    oidc_jwks_refresh_secs: u64,
+    /// Comma-separated list of JWT `origin` claim values allowed to
+    /// submit; if unset, any authenticated origin is allowed
+    #[clap(long, value_delimiter = ',')]
+    allowed_origins: Option<Vec<String>>,
+    /// Maximum burst size of the per-origin submission rate limit token
+    /// bucket
+    #[clap(long, default_value = "60")]
+    rate_limit_capacity: u32,
+    /// Sustained per-origin submission rate, in tokens refilled per second
+    #[clap(long, default_value = "1.0")]
+    rate_limit_refill_per_sec: f64,
+    /// Comma-separated list of origins (scheme://host[:port]) to send
+    /// CORS headers for, so browser-based submitters can call this API
+    /// directly; pass `*` to allow any origin. Unset disables CORS.
+    #[clap(long, value_delimiter = ',')]
+    cors_origin: Option<Vec<String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct SubmissionStatus {
     id: String,
     status: String,
     message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    errors: Option<Vec<validate::FieldError>>,
+}
+
+// Builds the CORS layer for `--cors-origin`, if set. `*` is handled
+// separately since `AllowOrigin::list` does not accept a wildcard entry;
+// any `*` in the list allows every origin via `AllowOrigin::any()`.
+fn build_cors_layer(origins: &[String]) -> CorsLayer {
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        let parsed: Vec<HeaderValue> = origins
+            .iter()
+            .filter_map(|o| match o.parse() {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    eprintln!("Warning: ignoring invalid --cors-origin value {}: {}", o, e);
+                    None
+                }
+            })
+            .collect();
+        AllowOrigin::list(parsed)
+    };
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
 }
 
 use std::sync::atomic::{AtomicU64, Ordering};
 
 struct AppState {
-    directory: String,
+    spool: Arc<dyn spool::SpoolBackend>,
     jwt_secret: String,
+    jwks: Option<Arc<jwks::JwksStore>>,
+    oidc: Option<OidcAuth>,
+    allowed_origins: Option<HashSet<String>>,
+    rate_limiter: ratelimit::RateLimiter,
+    origin_metrics: Mutex<HashMap<String, OriginMetrics>>,
     submission_counter: AtomicU64,
     error_counter: AtomicU64,
 }
 
+/// Per-origin submission/rejection counts, keyed on the JWT `origin`
+/// claim, surfaced as labeled series on `/metrics`.
+#[derive(Debug, Default, Clone, Copy)]
+struct OriginMetrics {
+    submitted: u64,
+    rejected: u64,
+}
+
+impl AppState {
+    fn record_origin_submitted(&self, origin: &str) {
+        self.origin_metrics
+            .lock()
+            .unwrap()
+            .entry(origin.to_string())
+            .or_default()
+            .submitted += 1;
+    }
+
+    fn record_origin_rejected(&self, origin: &str) {
+        self.origin_metrics
+            .lock()
+            .unwrap()
+            .entry(origin.to_string())
+            .or_default()
+            .rejected += 1;
+    }
+}
+
+// Prometheus label values can't contain an unescaped `"`, `\` or newline.
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
 fn verify_submission_path(path: &str) -> bool {
     let path = Path::new(path);
     path.exists() && path.is_dir()
@@ -102,11 +232,7 @@ async fn submission_metrics(
     headers: HeaderMap,
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    let spool_path = Path::new(&state.directory);
-    let json_files_num = match spool_path.read_dir() {
-        Ok(entries) => entries.filter_map(Result::ok).filter(|e| e.path().extension().map_or(false, |ext| ext == "json")).count(),
-        Err(_) => 0,
-    };
+    let json_files_num = state.spool.count().await.unwrap_or(0);
     // Prometheus metrics format
     // String to hold the metrics
     let mut metrics = String::new();
@@ -130,22 +256,128 @@ async fn submission_metrics(
         json_files_num
     ));
 
+    let origin_metrics = state.origin_metrics.lock().unwrap();
+    metrics.push_str("# HELP kcdb_submissions_by_origin_total Total number of submissions accepted, by origin\n");
+    metrics.push_str("# TYPE kcdb_submissions_by_origin_total counter\n");
+    for (origin, counts) in origin_metrics.iter() {
+        metrics.push_str(&format!(
+            "kcdb_submissions_by_origin_total{{origin=\"{}\"}} {}\n",
+            escape_label_value(origin),
+            counts.submitted
+        ));
+    }
+    metrics.push_str("# HELP kcdb_rejections_by_origin_total Total number of submissions rejected (authorization or rate limit), by origin\n");
+    metrics.push_str("# TYPE kcdb_rejections_by_origin_total counter\n");
+    for (origin, counts) in origin_metrics.iter() {
+        metrics.push_str(&format!(
+            "kcdb_rejections_by_origin_total{{origin=\"{}\"}} {}\n",
+            escape_label_value(origin),
+            counts.rejected
+        ));
+    }
+
     (StatusCode::OK, metrics)
 }
 
+// Serves the key authorization for an ACME HTTP-01 challenge token.
+async fn acme_challenge_response(
+    AxumPath(token): AxumPath<String>,
+    State(challenges): State<acme::ChallengeStore>,
+) -> impl IntoResponse {
+    match challenges.get(&token) {
+        Some(key_authorization) => (StatusCode::OK, key_authorization),
+        None => (StatusCode::NOT_FOUND, String::new()),
+    }
+}
+
 #[tokio::main]
 async fn main() {
     let limit_layer = RequestBodyLimitLayer::new(512 * 1024 * 1024);
     let args = Args::parse();
     let jwt_secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| args.jwt_secret.clone());
+    let jwks = match (&args.jwks_path, &args.jwks_url) {
+        (Some(path), _) => Some(Arc::new(jwks::JwksStore::from_file(path.clone()))),
+        (None, Some(url)) => Some(Arc::new(jwks::JwksStore::from_url(url.clone()))),
+        (None, None) => None,
+    };
+    if let Some(store) = &jwks {
+        if let Err(e) = store.refresh().await {
+            eprintln!("Warning: initial JWKS load failed: {}", e);
+        }
+        jwks::JwksStore::spawn_refresh_task(
+            store.clone(),
+            Duration::from_secs(args.jwks_refresh_secs),
+        );
+    }
+    let oidc = match &args.oidc_issuer {
+        Some(issuer) => {
+            let audience = args.oidc_audience.clone().unwrap_or_else(|| {
+                eprintln!("Error: --oidc-audience is required when --oidc-issuer is set");
+                std::process::exit(1);
+            });
+            let store = match jwks::JwksStore::from_oidc_issuer(issuer).await {
+                Ok(store) => Arc::new(store),
+                Err(e) => {
+                    eprintln!("Error: OIDC discovery for issuer {} failed: {}", issuer, e);
+                    std::process::exit(1);
+                }
+            };
+            if let Err(e) = store.refresh().await {
+                eprintln!("Warning: initial OIDC JWKS load failed: {}", e);
+            }
+            jwks::JwksStore::spawn_refresh_task(
+                store.clone(),
+                Duration::from_secs(args.oidc_jwks_refresh_secs),
+            );
+            Some(OidcAuth {
+                jwks: store,
+                issuer: issuer.clone(),
+                audience,
+            })
+        }
+        None => None,
+    };
+    let directory = args.directory.clone();
+    let spool: Arc<dyn spool::SpoolBackend> = if let Some(bucket) = args.spool_s3_bucket.clone() {
+        println!("Using S3 spool backend (bucket: {})", bucket);
+        Arc::new(
+            spool::S3SpoolBackend::new(
+                bucket,
+                args.spool_s3_endpoint.clone(),
+                args.spool_s3_region.clone(),
+            )
+            .await,
+        )
+    } else {
+        if !verify_submission_path(&directory) {
+            eprintln!(
+                "Error: submissions path {} does not exist or is not a directory",
+                directory
+            );
+            std::process::exit(1);
+        }
+        Arc::new(spool::FsSpoolBackend::new(directory.clone()))
+    };
     let app_state = Arc::new(AppState {
-        directory: args.directory,
+        spool,
         jwt_secret: jwt_secret,
+        jwks,
+        oidc,
+        allowed_origins: args
+            .allowed_origins
+            .clone()
+            .map(|origins| origins.into_iter().collect()),
+        rate_limiter: ratelimit::RateLimiter::new(
+            args.rate_limit_capacity,
+            args.rate_limit_refill_per_sec,
+        ),
+        origin_metrics: Mutex::new(HashMap::new()),
         submission_counter: AtomicU64::new(0),
         error_counter: AtomicU64::new(0),
     });
     let tls_key: String;
     let tls_chain: String;
+    let mut acme_manager: Option<Arc<acme::AcmeManager>> = None;
     // print if JWT_SECRET is set in env
     if let Ok(_jwt_secret) = std::env::var("JWT_SECRET") {
         println!("Using JWT secret from environment variable");
@@ -168,17 +400,46 @@ async fn main() {
             eprintln!("Error: TLS key file {} does not exist", tls_key);
             std::process::exit(1);
         }
+    } else if let Some(domain) = args.acme_domain.clone() {
+        let email = args.acme_email.clone().unwrap_or_else(|| {
+            eprintln!("Error: --acme-email is required when --acme-domain is set");
+            std::process::exit(1);
+        });
+        let cert_dir = format!("{}/acme", directory);
+        let manager = Arc::new(acme::AcmeManager::new(domain, email, cert_dir));
+        // Serve HTTP-01 challenge responses on plain port 80 in the background
+        // while the order is being finalized.
+        let challenge_app = Router::new()
+            .route(
+                "/.well-known/acme-challenge/:token",
+                get(acme_challenge_response),
+            )
+            .with_state(manager.challenges());
+        let challenge_listener = match TcpListener::bind(("0.0.0.0", 80)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("Error: failed to bind ACME challenge listener on port 80: {}", e);
+                std::process::exit(1);
+            }
+        };
+        tokio::spawn(async move {
+            axum::serve(challenge_listener, challenge_app).await.unwrap();
+        });
+        match manager.ensure_certificate().await {
+            Ok((cert_path, key_path)) => {
+                tls_chain = cert_path;
+                tls_key = key_path;
+            }
+            Err(e) => {
+                eprintln!("Error: ACME certificate provisioning failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        acme_manager = Some(manager);
     } else {
         tls_key = String::new();
         tls_chain = String::new();
     }
-    if !verify_submission_path(&app_state.directory) {
-        eprintln!(
-            "Error: submissions path {} does not exist or is not a directory",
-            app_state.directory
-        );
-        std::process::exit(1);
-    }
     // if default value - warn
     if app_state.jwt_secret == "secret" {
         eprintln!("Warning: JWT secret is default value");
@@ -189,16 +450,25 @@ async fn main() {
     }
     println!(
         "Listening on {}:{}, submissions path: {}",
-        args.host, args.port, app_state.directory
+        args.host, args.port, directory
     );
+    let cors_layer = args.cors_origin.as_deref().map(build_cors_layer);
+    if let Some(origins) = &args.cors_origin {
+        println!("CORS enabled for origin(s): {}", origins.join(", "));
+    }
     // plain http if tls_key is empty
     if tls_key.is_empty() {
         println!("Starting HTTP server");
-        let app = Router::new()
+        let mut app = Router::new()
             .route("/submit", post(receive_submission))
+            .route("/status", get(submission_status))
+            .route("/metrics", get(submission_metrics))
             .with_state(app_state)
             .layer(limit_layer)
             .layer(axum::extract::DefaultBodyLimit::max(512 * 1024 * 1024));
+        if let Some(cors) = cors_layer {
+            app = app.layer(cors);
+        }
         let tcp_listener = TcpListener::bind((args.host, args.port)).await.unwrap();
         axum::serve(tcp_listener, app).await.unwrap();
     } else {
@@ -206,17 +476,23 @@ async fn main() {
             "Starting HTTPS server with TLS key: {} and chain: {}",
             tls_key, tls_chain
         );
-        let app = Router::new()
+        let mut app = Router::new()
             .route("/submit", post(receive_submission))
             .route("/status", get(submission_status))
             .route("/metrics", get(submission_metrics))
             .with_state(app_state)
             .layer(limit_layer)
             .layer(axum::extract::DefaultBodyLimit::max(512 * 1024 * 1024));
+        if let Some(cors) = cors_layer {
+            app = app.layer(cors);
+        }
         //let tcp_listener = TcpListener::bind((args.host, args.port)).await.unwrap();
         let tls_config = RustlsConfig::from_pem_file(tls_chain, tls_key)
             .await
             .unwrap();
+        if let Some(manager) = acme_manager {
+            manager.spawn_renewal_task(tls_config.clone());
+        }
         let address = format!("{}:{}", args.host, args.port);
         let addr = SocketAddr::from(address.parse::<std::net::SocketAddr>().unwrap());
         axum_server::bind_rustls(addr, tls_config)
@@ -226,10 +502,10 @@ async fn main() {
     }
 }
 
-fn verify_auth(headers: HeaderMap, state: Arc<AppState>) -> Result<(), String> {
-    // if secret is empty, return Ok
-    if state.jwt_secret.is_empty() {
-        return Ok(());
+async fn verify_auth(headers: HeaderMap, state: Arc<AppState>) -> Result<Claims, String> {
+    // if no verification method is configured at all, treat auth as disabled
+    if state.jwt_secret.is_empty() && state.jwks.is_none() && state.oidc.is_none() {
+        return Ok(Claims::default());
     }
     let jwt_r = headers.get("Authorization");
     let jwt = match jwt_r {
@@ -246,10 +522,12 @@ fn verify_auth(headers: HeaderMap, state: Arc<AppState>) -> Result<(), String> {
         Some(jwt_str) => jwt_str,
         None => return Err("Missing or invalid JWT (Bearer)".to_string()),
     };
-    let jwt = verify_jwt(jwt_str, &state.jwt_secret);
-    match jwt {
-        Ok(_jwt) => Ok(()),
-        Err(e) => Err(e.to_string()),
+    if let Some(oidc) = &state.oidc {
+        return verify_jwt_oidc(jwt_str, oidc).await;
+    }
+    match &state.jwks {
+        Some(store) => verify_jwt_jwks(jwt_str, store).await,
+        None => verify_jwt(jwt_str, &state.jwt_secret).map_err(|e| e.to_string()),
     }
 }
 
@@ -258,6 +536,7 @@ fn generate_answer(status: &str, id: &str, message: Option<String>) -> String {
         id: id.to_string(),
         status: status.to_string(),
         message: message,
+        ..Default::default()
     };
     // serialize to json
     let jsonstr = serde_json::to_string(&status).unwrap();
@@ -272,9 +551,9 @@ async fn submission_status(
     State(state): State<Arc<AppState>>,
     id: String,
 ) -> impl IntoResponse {
-    let auth_result = verify_auth(headers, state.clone());
+    let auth_result = verify_auth(headers, state.clone()).await;
     match auth_result {
-        Ok(()) => (),
+        Ok(_claims) => (),
         Err(e) => {
             println!("Error: {}", e);
             let jsanswer = generate_answer("error", "0", Some(e));
@@ -293,9 +572,8 @@ async fn submission_status(
         return (StatusCode::BAD_REQUEST, jsanswer);
     }
 
-    let submission_file = format!("{}/submission-{}.json.temp", state.directory, id);
-    // check if the file exists
-    if !Path::new(&submission_file).exists() {
+    let exists = state.spool.exists(&id).await.unwrap_or(false);
+    if !exists {
         let jsanswer = generate_answer("notfound", id.as_str(), Some("File not found".to_string()));
         return (StatusCode::NOT_FOUND, jsanswer);
     }
@@ -311,40 +589,150 @@ async fn receive_submission(
     State(state): State<Arc<AppState>>,
     body: String,
 ) -> impl IntoResponse {
-    let auth_result = verify_auth(headers, state.clone());
-    match auth_result {
-        Ok(()) => (),
+    let auth_result = verify_auth(headers, state.clone()).await;
+    let claims = match auth_result {
+        Ok(claims) => claims,
         Err(e) => {
             println!("Error: {}", e);
             let err_status = SubmissionStatus {
                 id: "0".to_string(),
                 status: "error".to_string(),
                 message: Some(e),
+                ..Default::default()
             };
             let err_json = serde_json::to_string(&err_status).unwrap();
             // increment error counter atomically
             state.error_counter.fetch_add(1, Ordering::Relaxed);
 
-            return (StatusCode::UNAUTHORIZED, err_json);
+            return (StatusCode::UNAUTHORIZED, err_json).into_response();
+        }
+    };
+
+    // Per-origin authorization and rate limiting are both keyed on the
+    // JWT `origin` claim. Auth being enabled at all (rather than a token
+    // just happening to be absent) is what makes a missing claim
+    // meaningful: an authenticated token without an `origin` (e.g. an
+    // OIDC token that never carried one) must not silently skip the
+    // allowlist, or the allowlist is trivially evaded.
+    let auth_enabled = !(state.jwt_secret.is_empty() && state.jwks.is_none() && state.oidc.is_none());
+    if auth_enabled && state.allowed_origins.is_some() && claims.origin.is_none() {
+        state.error_counter.fetch_add(1, Ordering::Relaxed);
+        state.record_origin_rejected("unknown");
+        let err_status = SubmissionStatus {
+            id: "0".to_string(),
+            status: "error".to_string(),
+            message: Some("JWT is missing the origin claim required for authorization".to_string()),
+            ..Default::default()
+        };
+        let err_json = serde_json::to_string(&err_status).unwrap();
+        return (StatusCode::FORBIDDEN, err_json).into_response();
+    }
+    if let Some(origin) = claims.origin.as_deref() {
+        if let Some(allowed) = &state.allowed_origins {
+            if !allowed.contains(origin) {
+                state.error_counter.fetch_add(1, Ordering::Relaxed);
+                state.record_origin_rejected(origin);
+                let err_status = SubmissionStatus {
+                    id: "0".to_string(),
+                    status: "error".to_string(),
+                    message: Some(format!("Origin {} is not authorized to submit", origin)),
+                    ..Default::default()
+                };
+                let err_json = serde_json::to_string(&err_status).unwrap();
+                return (StatusCode::FORBIDDEN, err_json).into_response();
+            }
+        }
+        if let Err(retry_after) = state.rate_limiter.try_acquire(origin) {
+            state.error_counter.fetch_add(1, Ordering::Relaxed);
+            state.record_origin_rejected(origin);
+            let err_status = SubmissionStatus {
+                id: "0".to_string(),
+                status: "error".to_string(),
+                message: Some(format!("Rate limit exceeded for origin {}", origin)),
+                ..Default::default()
+            };
+            let err_json = serde_json::to_string(&err_status).unwrap();
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.as_secs().max(1).to_string())],
+                err_json,
+            )
+                .into_response();
         }
     }
 
     let submission_json = serde_json::from_str::<serde_json::Value>(&body);
     match submission_json {
-        Ok(_submission) => {
+        Ok(submission) => {
+            let validation_errors = validate::validate_submission(&submission);
+            if !validation_errors.is_empty() {
+                state.error_counter.fetch_add(1, Ordering::Relaxed);
+                let err_status = SubmissionStatus {
+                    id: "0".to_string(),
+                    status: "error".to_string(),
+                    message: Some("Submission failed validation".to_string()),
+                    errors: Some(validation_errors),
+                };
+                let err_json = serde_json::to_string(&err_status).unwrap();
+                return (StatusCode::BAD_REQUEST, err_json).into_response();
+            }
             let size = body.len();
             println!("Received submission size: {}", size);
             let submission_id = random_string(32);
-            let submission_file =
-                format!("{}/submission-{}.json.temp", state.directory, submission_id);
-            std::fs::write(&submission_file, &body).unwrap();
-            // on completion, rename to submission.json
-            std::fs::rename(
-                &submission_file,
-                &format!("{}/submission-{}.json", state.directory, submission_id),
-            )
-            .unwrap();
+            if let Some(submitter) = claims.email.as_deref().or(claims.sub.as_deref()) {
+                println!("Submission {} attributed to {}", submission_id, submitter);
+            } else if let Some(origin) = claims.origin.as_deref() {
+                println!("Submission {} attributed to origin {}", submission_id, origin);
+            }
+            if let Err(e) = state.spool.put_temp(&submission_id, body.as_bytes()).await {
+                eprintln!("Error: {}", e);
+                state.error_counter.fetch_add(1, Ordering::Relaxed);
+                let err_status = SubmissionStatus {
+                    id: "0".to_string(),
+                    status: "error".to_string(),
+                    message: Some(e),
+                    ..Default::default()
+                };
+                let err_json = serde_json::to_string(&err_status).unwrap();
+                return (StatusCode::INTERNAL_SERVER_ERROR, err_json).into_response();
+            }
+            // on completion, promote the staged submission to its final name
+            if let Err(e) = state.spool.commit(&submission_id).await {
+                eprintln!("Error: {}", e);
+                state.error_counter.fetch_add(1, Ordering::Relaxed);
+                let err_status = SubmissionStatus {
+                    id: "0".to_string(),
+                    status: "error".to_string(),
+                    message: Some(e),
+                    ..Default::default()
+                };
+                let err_json = serde_json::to_string(&err_status).unwrap();
+                return (StatusCode::INTERNAL_SERVER_ERROR, err_json).into_response();
+            }
             println!("Submission {} received", submission_id);
+            if claims.origin.is_some() || claims.sub.is_some() || claims.email.is_some() {
+                // persist the submitter identity alongside the spooled
+                // submission as a provenance sidecar, rather than only
+                // logging it, so it survives for later audit
+                let provenance = serde_json::json!({
+                    "origin": claims.origin,
+                    "sub": claims.sub,
+                    "email": claims.email,
+                });
+                let provenance_id = format!("{}-provenance", submission_id);
+                if let Err(e) = state
+                    .spool
+                    .put_temp(&provenance_id, provenance.to_string().as_bytes())
+                    .await
+                {
+                    eprintln!("Warning: failed to stage submitter provenance for {}: {}", submission_id, e);
+                } else if let Err(e) = state.spool.commit(&provenance_id).await {
+                    eprintln!("Warning: failed to commit submitter provenance for {}: {}", submission_id, e);
+                }
+            }
+            if let Some(origin) = claims.origin.as_deref() {
+                state.record_origin_submitted(origin);
+            }
             let msg = format!(
                 "Received submission {} with size {} bytes",
                 submission_id, size
@@ -354,12 +742,13 @@ async fn receive_submission(
                 id: submission_id,
                 status: "ok".to_string(),
                 message: Some(msg),
+                ..Default::default()
             };
             let jsonstr = serde_json::to_string(&status).unwrap();
             // increment submission counter atomically
             state.submission_counter.fetch_add(1, Ordering::Relaxed);
             println!("Submission status: {}", jsonstr);
-            (StatusCode::OK, jsonstr)
+            (StatusCode::OK, jsonstr).into_response()
         }
         Err(e) => {
             println!("Error: {}", e);
@@ -367,22 +756,77 @@ async fn receive_submission(
                 id: "0".to_string(),
                 status: "error".to_string(),
                 message: Some(e.to_string()),
+                ..Default::default()
             };
             let err_json = serde_json::to_string(&err_status).unwrap();
-            (StatusCode::BAD_REQUEST, err_json)
+            (StatusCode::BAD_REQUEST, err_json).into_response()
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct JWT {
-    origin: String,
-    gendate: String,
+// Claims we may want to read back out of a verified token. Legacy
+// HS256 submitter tokens only ever set `origin`/`gendate`; OIDC tokens
+// additionally carry `sub`/`email`, used for provenance. All fields are
+// optional so either style of token deserializes without error.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Claims {
+    #[serde(default)]
+    origin: Option<String>,
+    #[serde(default)]
+    gendate: Option<String>,
+    #[serde(default)]
+    sub: Option<String>,
+    #[serde(default)]
+    email: Option<String>,
 }
 
-fn verify_jwt(token: &str, secret: &str) -> Result<JWT, jsonwebtoken::errors::Error> {
+fn verify_jwt(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
     let key = DecodingKey::from_secret(secret.as_bytes());
-    let token = decode::<JWT>(token, &key, &Validation::default())?;
+    let token = decode::<Claims>(token, &key, &Validation::default())?;
+    Ok(token.claims)
+}
+
+// Verifies a JWT signed with a per-origin asymmetric key, selected by the
+// `kid` declared in the token's unverified JOSE header. Refreshes the
+// keyset on a `kid` cache miss so a freshly rotated key is picked up
+// without waiting for the next scheduled refresh.
+async fn verify_jwt_jwks(token: &str, store: &jwks::JwksStore) -> Result<Claims, String> {
+    let header = decode_header(token).map_err(|e| e.to_string())?;
+    let kid = header.kid.ok_or_else(|| "JWT is missing a kid".to_string())?;
+    let key = store
+        .get_or_refresh(&kid)
+        .await
+        .ok_or_else(|| format!("no JWKS key for kid {}", kid))?;
+    let validation = Validation::new(key.algorithm);
+    let token =
+        decode::<Claims>(token, &key.decoding_key, &validation).map_err(|e| e.to_string())?;
+    Ok(token.claims)
+}
+
+/// OIDC bearer-token verification: keys come from the provider's published
+/// JWKS, and `iss`/`aud`/`exp`/`nbf` are enforced on top of the signature
+/// (unlike the bare `Validation::default()` the HS256 path uses, which
+/// never checks audience).
+struct OidcAuth {
+    jwks: Arc<jwks::JwksStore>,
+    issuer: String,
+    audience: String,
+}
+
+async fn verify_jwt_oidc(token: &str, oidc: &OidcAuth) -> Result<Claims, String> {
+    let header = decode_header(token).map_err(|e| e.to_string())?;
+    let kid = header.kid.ok_or_else(|| "JWT is missing a kid".to_string())?;
+    let key = oidc
+        .jwks
+        .get_or_refresh(&kid)
+        .await
+        .ok_or_else(|| format!("no JWKS key for kid {}", kid))?;
+    let mut validation = Validation::new(key.algorithm);
+    validation.set_issuer(&[&oidc.issuer]);
+    validation.set_audience(&[&oidc.audience]);
+    validation.validate_nbf = true;
+    let token =
+        decode::<Claims>(token, &key.decoding_key, &validation).map_err(|e| e.to_string())?;
     Ok(token.claims)
 }
 
@@ -390,7 +834,7 @@ fn verify_jwt(token: &str, secret: &str) -> Result<JWT, jsonwebtoken::errors::Er
 /*
 fn generate_jwt(origin: &str, gendate: &str, secret: &str) -> Result<String, jsonwebtoken::errors::Error> {
     let key = EncodingKey::from_secret(secret.as_bytes());
-    let token = encode(&Header::default(), &JWT { origin: origin.to_string(), gendate: gendate.to_string() }, &key)?;
+    let token = encode(&Header::default(), &Claims { origin: Some(origin.to_string()), gendate: Some(gendate.to_string()), ..Default::default() }, &key)?;
     Ok(token)
 }
 */
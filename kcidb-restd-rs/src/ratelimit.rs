@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: LGPL-2.1-only
+// Copyright (C) 2025 Collabora Ltd
+// Author: Denys Fedoryshchenko <denys.f@collabora.com>
+//
+// This library is free software; you can redistribute it and/or modify it under
+// the terms of the GNU Lesser General Public License as published by the Free
+// Software Foundation; version 2.1.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+// PARTICULAR PURPOSE. See the GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License along
+// with this library; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA
+
+/*
+Per-origin token-bucket rate limiting.
+
+Each JWT `origin` claim gets its own bucket, so one noisy submitter can't
+starve the others. Buckets refill lazily (tokens are topped up based on
+elapsed time whenever the origin is next seen) rather than via a
+background task, since the interesting quantity is wall-clock time
+between submissions, not a fixed tick.
+
+The bucket map is a single `Mutex<HashMap<...>>` rather than a sharded
+map like `DashMap`, so acquires across different origins serialize on
+one lock. That's an intentional simplification given this server's
+submission volume; revisit if origin count and request rate grow enough
+for the lock to show up as contention.
+*/
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Fallback `Retry-After` when the bucket is configured with a zero (or
+/// negative) refill rate, so it never actually recovers on its own.
+const STALLED_BUCKET_RETRY_SECS: f64 = 3600.0;
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token-bucket rate limiter keyed by JWT `origin` claim.
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(capacity: u32, refill_per_sec: f64) -> Self {
+        RateLimiter {
+            capacity: capacity as f64,
+            refill_per_sec,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Try to consume one token from `origin`'s bucket. On success returns
+    /// `Ok(())`; if the bucket is empty, returns `Err` with the duration
+    /// until enough tokens will have refilled, suitable for a
+    /// `Retry-After` header.
+    pub fn try_acquire(&self, origin: &str) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        let now = Instant::now();
+        let bucket = buckets.entry(origin.to_string()).or_insert_with(|| Bucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.last_refill = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let deficit = 1.0 - bucket.tokens;
+            let retry_secs = if self.refill_per_sec > 0.0 {
+                (deficit / self.refill_per_sec).ceil().max(1.0)
+            } else {
+                STALLED_BUCKET_RETRY_SECS
+            };
+            Err(Duration::from_secs_f64(retry_secs))
+        }
+    }
+}
@@ -0,0 +1,230 @@
+// SPDX-License-Identifier: LGPL-2.1-only
+// Copyright (C) 2025 Collabora Ltd
+// Author: Denys Fedoryshchenko <denys.f@collabora.com>
+//
+// This library is free software; you can redistribute it and/or modify it under
+// the terms of the GNU Lesser General Public License as published by the Free
+// Software Foundation; version 2.1.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+// PARTICULAR PURPOSE. See the GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License along
+// with this library; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA
+
+/*
+Submission spool backends.
+
+`receive_submission` stages a submission under a `.temp`/`staging/` name and
+then promotes it to its final name once fully written, so a reader never
+observes a partially written file. `SpoolBackend` captures that put/commit
+split so the handlers can run against either the local filesystem or an
+S3-compatible bucket without knowing which one is in play. All methods are
+async so a slow backend (e.g. a bucket in another region) never blocks the
+Tokio runtime.
+*/
+
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait SpoolBackend: Send + Sync {
+    /// Stage a submission's bytes under `id`, not yet visible to `exists`.
+    async fn put_temp(&self, id: &str, data: &[u8]) -> Result<(), String>;
+
+    /// Promote a staged submission to its final, visible location.
+    async fn commit(&self, id: &str) -> Result<(), String>;
+
+    /// Whether a committed submission with the given `id` exists.
+    async fn exists(&self, id: &str) -> Result<bool, String>;
+
+    /// Number of committed submissions currently in the spool.
+    async fn count(&self) -> Result<usize, String>;
+}
+
+/// Suffix on a submission id that marks it as a provenance sidecar rather
+/// than an actual submission, so `count()` doesn't mistake one for the
+/// other.
+const PROVENANCE_SUFFIX: &str = "-provenance";
+
+/// Default backend: stages to `{directory}/submission-{id}.json.temp` and
+/// promotes with a rename, same layout the server has always used.
+pub struct FsSpoolBackend {
+    directory: String,
+}
+
+impl FsSpoolBackend {
+    pub fn new(directory: String) -> Self {
+        FsSpoolBackend { directory }
+    }
+
+    fn temp_path(&self, id: &str) -> String {
+        format!("{}/submission-{}.json.temp", self.directory, id)
+    }
+
+    fn final_path(&self, id: &str) -> String {
+        format!("{}/submission-{}.json", self.directory, id)
+    }
+}
+
+#[async_trait]
+impl SpoolBackend for FsSpoolBackend {
+    async fn put_temp(&self, id: &str, data: &[u8]) -> Result<(), String> {
+        tokio::fs::write(self.temp_path(id), data)
+            .await
+            .map_err(|e| format!("failed to write {}: {e}", self.temp_path(id)))
+    }
+
+    async fn commit(&self, id: &str) -> Result<(), String> {
+        tokio::fs::rename(self.temp_path(id), self.final_path(id))
+            .await
+            .map_err(|e| format!("failed to commit {}: {e}", self.final_path(id)))
+    }
+
+    async fn exists(&self, id: &str) -> Result<bool, String> {
+        Ok(tokio::fs::try_exists(self.final_path(id)).await.unwrap_or(false))
+    }
+
+    async fn count(&self) -> Result<usize, String> {
+        let mut entries = tokio::fs::read_dir(&self.directory)
+            .await
+            .map_err(|e| format!("failed to read {}: {e}", self.directory))?;
+        let mut count = 0;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| format!("failed to read directory entry: {e}"))?
+        {
+            let is_submission = entry.path().extension().map_or(false, |ext| ext == "json")
+                && entry
+                    .path()
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|s| !s.ends_with(PROVENANCE_SUFFIX));
+            if is_submission {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// S3-compatible backend. S3 has no atomic rename, so `commit` emulates
+/// the filesystem backend's `.temp` promotion with a server-side copy from
+/// the `staging/` prefix to the `submissions/` prefix followed by a delete
+/// of the staging object.
+pub struct S3SpoolBackend {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+}
+
+const STAGING_PREFIX: &str = "staging/";
+const SUBMISSIONS_PREFIX: &str = "submissions/";
+
+impl S3SpoolBackend {
+    pub async fn new(bucket: String, endpoint: Option<String>, region: String) -> Self {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .region(aws_sdk_s3::config::Region::new(region));
+        if let Some(endpoint) = endpoint {
+            loader = loader.endpoint_url(endpoint);
+        }
+        let config = loader.load().await;
+        let client = aws_sdk_s3::Client::new(&config);
+        S3SpoolBackend { client, bucket }
+    }
+
+    fn staging_key(&self, id: &str) -> String {
+        format!("{STAGING_PREFIX}submission-{id}.json")
+    }
+
+    fn final_key(&self, id: &str) -> String {
+        format!("{SUBMISSIONS_PREFIX}submission-{id}.json")
+    }
+}
+
+#[async_trait]
+impl SpoolBackend for S3SpoolBackend {
+    async fn put_temp(&self, id: &str, data: &[u8]) -> Result<(), String> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(self.staging_key(id))
+            .body(data.to_vec().into())
+            .send()
+            .await
+            .map_err(|e| format!("failed to stage submission {id} in S3: {e}"))?;
+        Ok(())
+    }
+
+    async fn commit(&self, id: &str) -> Result<(), String> {
+        let copy_source = format!("{}/{}", self.bucket, self.staging_key(id));
+        self.client
+            .copy_object()
+            .bucket(&self.bucket)
+            .copy_source(&copy_source)
+            .key(self.final_key(id))
+            .send()
+            .await
+            .map_err(|e| format!("failed to promote submission {id} in S3: {e}"))?;
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(self.staging_key(id))
+            .send()
+            .await
+            .map_err(|e| format!("failed to clean up staged submission {id} in S3: {e}"))?;
+        Ok(())
+    }
+
+    async fn exists(&self, id: &str) -> Result<bool, String> {
+        match self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(self.final_key(id))
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(aws_sdk_s3::error::SdkError::ServiceError(e)) if e.err().is_not_found() => {
+                Ok(false)
+            }
+            Err(e) => Err(format!("failed to check submission {id} in S3: {e}")),
+        }
+    }
+
+    async fn count(&self) -> Result<usize, String> {
+        let mut count = 0;
+        let mut continuation_token = None;
+        loop {
+            let mut request = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(SUBMISSIONS_PREFIX);
+            if let Some(token) = continuation_token.take() {
+                request = request.continuation_token(token);
+            }
+            let response = request
+                .send()
+                .await
+                .map_err(|e| format!("failed to list submissions in S3: {e}"))?;
+            count += response
+                .contents()
+                .iter()
+                .filter(|o| {
+                    o.key().map_or(false, |k| {
+                        k.ends_with(".json") && !k.ends_with(&format!("{PROVENANCE_SUFFIX}.json"))
+                    })
+                })
+                .count();
+            if response.is_truncated().unwrap_or(false) {
+                continuation_token = response.next_continuation_token().map(|s| s.to_string());
+            } else {
+                break;
+            }
+        }
+        Ok(count)
+    }
+}
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: LGPL-2.1-only
+// Copyright (C) 2025 Collabora Ltd
+// Author: Denys Fedoryshchenko <denys.f@collabora.com>
+//
+// This library is free software; you can redistribute it and/or modify it under
+// the terms of the GNU Lesser General Public License as published by the Free
+// Software Foundation; version 2.1.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A
+// PARTICULAR PURPOSE. See the GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License along
+// with this library; if not, write to the Free Software Foundation, Inc.,
+// 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA
+
+/*
+Shallow, version-keyed validation of submitted KCIDB ingest documents.
+
+This does not replace full JSON schema validation against the KCIDB
+dataset schema (kept server-side, upstream of this receiver); it only
+catches the submission shapes a browser-based submitter is most likely to
+get wrong - a missing or unsupported `version`, or a top-level array field
+sent as something other than an array for the schema major version the
+document declares - and reports them as field-level errors instead of a
+single opaque "invalid JSON" message. It does not validate the shape of
+individual checkout/build/test/issue/incident objects.
+*/
+
+use serde_json::Value;
+
+/// Top-level array fields for schema major versions 1-3, before the
+/// "checkout" split and the "issues"/"incidents" tables were introduced
+/// in major version 4.
+const ARRAY_FIELDS_V1: &[&str] = &["revisions", "builds", "tests"];
+
+/// Top-level array fields from schema major version 4 onward.
+const ARRAY_FIELDS_V4: &[&str] = &["checkouts", "builds", "tests", "issues", "incidents"];
+
+/// Schema major versions this receiver knows how to validate.
+const SUPPORTED_MAJOR_VERSIONS: std::ops::RangeInclusive<u64> = 1..=5;
+
+/// A single field-level validation failure, reported back to the
+/// submitter so a browser client can point at what's wrong.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+fn field_error(field: &str, message: impl Into<String>) -> FieldError {
+    FieldError {
+        field: field.to_string(),
+        message: message.into(),
+    }
+}
+
+/// The set of top-level array fields valid for a given schema major
+/// version, or `None` if the major version isn't one this receiver knows
+/// how to validate.
+fn array_fields_for(major: u64) -> Option<&'static [&'static str]> {
+    if !SUPPORTED_MAJOR_VERSIONS.contains(&major) {
+        return None;
+    }
+    if major < 4 {
+        Some(ARRAY_FIELDS_V1)
+    } else {
+        Some(ARRAY_FIELDS_V4)
+    }
+}
+
+/// Validate the shape of a submitted ingest document against the rules for
+/// the schema major version it declares. Returns one `FieldError` per
+/// problem found; an empty vec means the document passed.
+pub fn validate_submission(value: &Value) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let Some(object) = value.as_object() else {
+        errors.push(field_error("", "submission must be a JSON object"));
+        return errors;
+    };
+
+    let major = match object.get("version") {
+        None => {
+            errors.push(field_error("version", "missing required field"));
+            None
+        }
+        Some(version) => match version.as_object() {
+            None => {
+                errors.push(field_error("version", "must be an object"));
+                None
+            }
+            Some(version) => match version.get("major") {
+                Some(Value::Number(major)) => match major.as_u64() {
+                    Some(major) => Some(major),
+                    None => {
+                        errors.push(field_error(
+                            "version.major",
+                            "must be a non-negative integer",
+                        ));
+                        None
+                    }
+                },
+                _ => {
+                    errors.push(field_error("version.major", "missing required number field"));
+                    None
+                }
+            },
+        },
+    };
+
+    let Some(major) = major else {
+        return errors;
+    };
+    let Some(array_fields) = array_fields_for(major) else {
+        errors.push(field_error(
+            "version.major",
+            format!(
+                "unsupported schema major version {major}; supported versions are {}-{}",
+                SUPPORTED_MAJOR_VERSIONS.start(),
+                SUPPORTED_MAJOR_VERSIONS.end()
+            ),
+        ));
+        return errors;
+    };
+
+    for field in array_fields {
+        if let Some(value) = object.get(*field) {
+            if !value.is_array() {
+                errors.push(field_error(field, "must be an array"));
+            }
+        }
+    }
+
+    // Per the KCIDB schema, `version` is the only required top-level
+    // field; a document carrying none of the data arrays (e.g. a
+    // checkout-only update split across several submissions) is valid.
+
+    errors
+}